@@ -1,22 +1,49 @@
 use arch_program::{
     account::AccountInfo,
+    clock::Clock,
     entrypoint,
     instruction::Instruction,
     msg,
     program::next_account_info,
     program_error::ProgramError,
     pubkey::Pubkey,
+    rent::Rent,
+    sysvar::slot_hashes,
     hash::{hash, Hash},
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 
+// Bound on `PlayerSpinState::history` so the account's serialized size — and
+// therefore its required allocation — has a fixed upper bound regardless of
+// how many spins have happened.
+pub const MAX_SPIN_HISTORY: usize = 31;
+
+// Epoch-keyed entries older than this are pruned from `PlayerSpinState`, so
+// that Vec stays bounded the same way vote-credit history does.
+pub const MAX_EPOCH_HISTORY: u64 = 5;
+
+// How many slots a reveal may trail its commit by. The `SlotHashes` sysvar
+// only retains a fixed-depth window of recent slots, so once more than this
+// many slots have passed the target slot's hash may have rolled out of it —
+// and re-deriving the target from whatever's oldest still in the sysvar at
+// that point would let a player pick their reveal timing to choose among
+// outcomes, exactly the grind commit-reveal is meant to prevent.
+pub const MAX_REVEAL_DELAY_SLOTS: u64 = 512;
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub enum WheelInstruction {
     InitializeWheel {
         prizes: Vec<String>,
         probabilities: Vec<u8>,
+        // Lamports paid out per prize slot, drawn from `escrow`.
+        payouts: Vec<u64>,
+        // Caps how many times a single player may spin per epoch.
+        max_spins_per_epoch: u32,
     },
+    // Writes an empty `PlayerSpinState` into a player's PDA ahead of their
+    // first spin. The PDA account must already exist and be owned by this
+    // program; this instruction does not allocate it.
+    InitializePlayerState,
     CommitSpin {
         commitment: [u8; 32], // Hash of user's secret value
     },
@@ -24,21 +51,77 @@ pub enum WheelInstruction {
         user_secret: [u8; 32], // Original secret value
     },
     ClaimPrize,
+    // Recompute a past spin's result from its recorded inputs and assert it
+    // reproduces what's stored in `history`, so anyone can audit the wheel
+    // without trusting the operator.
+    VerifySpin {
+        index: u8,
+    },
+    // Spin for several players in one instruction, amortizing transaction
+    // overhead for tournaments/airdrops. Restricted to the wheel's
+    // `authority`: unlike `CommitSpin`/`RevealSpin`, the randomness here is
+    // derived entirely within one transaction, so letting an arbitrary
+    // player trigger their own batch entry would let them grind entropy
+    // values against a result they can already see. One entropy value per
+    // player, matched up positionally with the trailing player/player-state
+    // account pairs, and each spin still consumes that player's epoch quota.
+    BatchSpin {
+        entropies: Vec<[u8; 32]>,
+    },
+}
+
+// A single past spin, kept around so its randomness can be re-derived and
+// checked on-chain. Modeled on the fixed-size lockout history in vote state.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct SpinRecord {
+    pub slot: u64,
+    pub block_hash: [u8; 32],
+    pub user_entropy: [u8; 32],
+    // Mixed into the randomness alongside the other fields, so two players'
+    // spins with identical slot/hash/entropy (e.g. a BatchSpin's shared slot hash)
+    // still land on independent outcomes and both CommitSpin/RevealSpin and
+    // BatchSpin records verify through the same derivation.
+    pub player: Pubkey,
+    pub result: usize,
+    pub timestamp: i64,
 }
 
-// Structure to store wheel state
+// Immutable wheel configuration. Every spin only *reads* this account, so
+// concurrent spins by different players no longer serialize on one hot
+// account the way a single shared `WheelState` would.
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
-pub struct WheelState {
+pub struct WheelConfig {
     pub initialized: bool,
     pub prizes: Vec<String>,
     pub probabilities: Vec<u8>,
-    pub last_spin_result: Option<usize>,
-    pub total_spins: u64,
+    // Lamports paid out per prize slot, debited from `escrow` on claim.
+    pub payouts: Vec<u64>,
     pub authority: Pubkey,
-    // Store verification data
-    pub last_slot: u64,
-    pub last_block_hash: [u8; 32],
+    // Program-owned account that funds prize payouts.
+    pub escrow: Pubkey,
+    // Caps how many times a single player may spin per epoch.
+    pub max_spins_per_epoch: u32,
+}
+
+// Per-player spin state, stored in an account derived from `(wheel,
+// player)`. `process_commit_spin`/`process_reveal_spin` mutate only the caller's
+// own `PlayerSpinState`, keeping `WheelConfig` read-only on the hot path.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct PlayerSpinState {
+    pub initialized: bool,
+    pub wheel: Pubkey,
+    pub player: Pubkey,
     pub user_commitment: [u8; 32],
+    // Slot the commitment was recorded at; the reveal is bound to the slot
+    // hash of the first slot after this one, so the player can't pick a
+    // slot hash they already know when they commit.
+    pub commit_slot: u64,
+    // Whether the most recent spin in `history` has already been paid out.
+    pub prize_claimed: bool,
+    // Bounded, newest-last record of past spins, capped at `MAX_SPIN_HISTORY`.
+    pub history: Vec<SpinRecord>,
+    // (epoch, spins taken that epoch), pruned to `MAX_EPOCH_HISTORY` epochs.
+    pub epoch_spins: Vec<(u64, u32)>,
 }
 
 entrypoint!(process_instruction);
@@ -52,18 +135,34 @@ pub fn process_instruction(
         .map_err(|_| ProgramError::InvalidInstructionData)?;
 
     match instruction {
-        WheelInstruction::InitializeWheel { prizes, probabilities } => {
+        WheelInstruction::InitializeWheel { prizes, probabilities, payouts, max_spins_per_epoch } => {
             msg!("Initializing Wheel");
-            process_initialize(program_id, accounts, prizes, probabilities)
+            process_initialize(program_id, accounts, prizes, probabilities, payouts, max_spins_per_epoch)
+        }
+        WheelInstruction::InitializePlayerState => {
+            msg!("Initializing Player State");
+            process_initialize_player_state(program_id, accounts)
+        }
+        WheelInstruction::CommitSpin { commitment } => {
+            msg!("Committing Spin");
+            process_commit_spin(program_id, accounts, commitment)
         }
-        WheelInstruction::SpinWheel => {
-            msg!("Spinning Wheel");
-            process_spin(program_id, accounts)
+        WheelInstruction::RevealSpin { user_secret } => {
+            msg!("Revealing Spin");
+            process_reveal_spin(program_id, accounts, user_secret)
         }
         WheelInstruction::ClaimPrize => {
             msg!("Claiming Prize");
             process_claim_prize(program_id, accounts)
         }
+        WheelInstruction::VerifySpin { index } => {
+            msg!("Verifying Spin");
+            process_verify_spin(program_id, accounts, index)
+        }
+        WheelInstruction::BatchSpin { entropies } => {
+            msg!("Batch Spinning Wheel");
+            process_batch_spin(program_id, accounts, entropies)
+        }
     }
 }
 
@@ -72,10 +171,13 @@ fn process_initialize(
     accounts: &[AccountInfo],
     prizes: Vec<String>,
     probabilities: Vec<u8>,
+    payouts: Vec<u64>,
+    max_spins_per_epoch: u32,
 ) -> Result<(), ProgramError> {
     let account_info_iter = &mut accounts.iter();
     let wheel_account = next_account_info(account_info_iter)?;
     let authority = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
 
     if !authority.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
@@ -86,111 +188,279 @@ fn process_initialize(
         return Err(ProgramError::InvalidArgument);
     }
 
-    // Validate prizes and probabilities have same length
-    if prizes.len() != probabilities.len() {
+    // Validate prizes, probabilities and payouts have the same length
+    if prizes.len() != probabilities.len() || prizes.len() != payouts.len() {
         return Err(ProgramError::InvalidArgument);
     }
 
-    let wheel_state = WheelState {
+    // The escrow must be owned by this program, so only `process_claim_prize`
+    // can move lamports out of it, and must already hold enough to cover the
+    // largest possible payout plus enough on top of that to stay rent-exempt.
+    if *escrow_account.owner != *program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    let max_payout = payouts.iter().copied().max().unwrap_or(0);
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(escrow_account.data_len());
+    if escrow_account.lamports() < max_payout.saturating_add(rent_exempt_minimum) {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    let wheel_config = WheelConfig {
         initialized: true,
         prizes,
         probabilities,
-        last_spin_result: None,
-        total_spins: 0,
+        payouts,
         authority: *authority.key,
+        escrow: *escrow_account.key,
+        max_spins_per_epoch,
     };
 
-    wheel_state.serialize(&mut *wheel_account.try_borrow_mut_data()?).map_err(map_to_program_error)?;
+    wheel_config.serialize(&mut *wheel_account.try_borrow_mut_data()?).map_err(map_to_program_error)?;
     Ok(())
 }
 
-fn map_to_program_error(error: std::io::Error) -> ProgramError {
-    msg!("Serialization error: {}", error);
-    ProgramError::InvalidAccountData
+// Seed prefix for the `(wheel, player)`-derived `PlayerSpinState` PDA.
+const PLAYER_STATE_SEED: &[u8] = b"player";
+
+/// Re-derive the expected `PlayerSpinState` address for `(wheel, player)`
+/// and check `account` actually is it and is owned by this program, so a
+/// player can't point spins at an arbitrary account they control instead of
+/// their one rate-limited PDA.
+fn check_player_state_address(
+    account: &AccountInfo,
+    program_id: &Pubkey,
+    wheel: &Pubkey,
+    player: &Pubkey,
+) -> Result<(), ProgramError> {
+    let (expected, _bump) =
+        Pubkey::find_program_address(&[PLAYER_STATE_SEED, wheel.as_ref(), player.as_ref()], program_id);
+
+    if *account.key != expected {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if *account.owner != *program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    Ok(())
 }
 
-fn process_spin(
+fn process_initialize_player_state(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    user_entropy: [u8; 32],
 ) -> Result<(), ProgramError> {
     let account_info_iter = &mut accounts.iter();
     let wheel_account = next_account_info(account_info_iter)?;
     let player = next_account_info(account_info_iter)?;
-    let recent_blockhash_account = next_account_info(account_info_iter)?;
+    let player_state_account = next_account_info(account_info_iter)?;
 
     if !player.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let mut wheel_state = WheelState::try_from_slice(&wheel_account.try_borrow_data()?)?;
-    
-    if !wheel_state.initialized {
-        return Err(ProgramError::UninitializedAccount);
-    }
-
-    // Get current block hash
-    let current_blockhash: [u8; 32] = recent_blockhash_account
-        .try_borrow_data()?
-        .try_into()
-        .map_err(|_| ProgramError::InvalidAccountData)?;
-
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    check_player_state_address(player_state_account, program_id, wheel_account.key, player.key)?;
 
-    let (random_value, final_hash) = get_verifiable_random_value(
-        &current_blockhash,
-        &user_entropy,
-        timestamp,
-        &wheel_state.probabilities
-    );
+    let player_state = PlayerSpinState {
+        initialized: true,
+        wheel: *wheel_account.key,
+        player: *player.key,
+        user_commitment: [0u8; 32],
+        commit_slot: 0,
+        prize_claimed: false,
+        history: Vec::new(),
+        epoch_spins: Vec::new(),
+    };
 
-    // Store all randomness components for verification
-    wheel_state.last_block_hash = current_blockhash;
-    wheel_state.user_entropy = user_entropy;
-    wheel_state.spin_timestamp = timestamp;
-    wheel_state.last_spin_result = Some(random_value);
-    wheel_state.total_spins += 1;
-
-    wheel_state.serialize(&mut *wheel_account.try_borrow_mut_data()?)?;
-    
-    msg!("Wheel spin result: {}", wheel_state.prizes[random_value]);
-    msg!("Verification hash: {}", hex::encode(final_hash));
-    
+    player_state.serialize(&mut *player_state_account.try_borrow_mut_data()?).map_err(map_to_program_error)?;
     Ok(())
 }
 
+fn map_to_program_error(error: std::io::Error) -> ProgramError {
+    msg!("Serialization error: {}", error);
+    ProgramError::InvalidAccountData
+}
+
+/// Derive a spin's outcome from its recorded inputs. Mixing in `player`
+/// keeps this the single derivation for both an individual reveal and a
+/// BatchSpin entry — otherwise two spins sharing a slot hash (as every
+/// entry in one BatchSpin call does) could collide, and `VerifySpin` would
+/// need to guess which derivation produced a given record.
 fn get_verifiable_random_value(
     slot: u64,
     block_hash: &[u8; 32],
-    user_secret: &[u8; 32],
-    probabilities: &[u8]
+    user_entropy: &[u8; 32],
+    player: &Pubkey,
+    probabilities: &[u8],
 ) -> (usize, [u8; 32]) {
-    // Combine inputs deterministically
-    let mut combined = [0u8; 32 + 32 + 8];
+    let mut combined = [0u8; 32 + 32 + 32 + 8];
     combined[0..32].copy_from_slice(block_hash);
-    combined[32..64].copy_from_slice(user_secret);
-    combined[64..].copy_from_slice(&slot.to_le_bytes());
-    
-    // Hash the combined value
+    combined[32..64].copy_from_slice(user_entropy);
+    combined[64..96].copy_from_slice(player.as_ref());
+    combined[96..].copy_from_slice(&slot.to_le_bytes());
+
     let hash_result = hash(&combined).to_bytes();
-    
-    // Use first 8 bytes for the random number
+    (select_prize_index(&hash_result, probabilities), hash_result)
+}
+
+/// Use the first 8 bytes of a verification hash to pick a prize index using
+/// weighted probabilities (each entry out of 100).
+fn select_prize_index(hash_result: &[u8; 32], probabilities: &[u8]) -> usize {
     let random_bytes: [u8; 8] = hash_result[0..8].try_into().unwrap();
     let random_number = u64::from_le_bytes(random_bytes) % 100;
-    
-    // Select prize using weighted probabilities
+
     let mut cumulative = 0;
     for (index, &probability) in probabilities.iter().enumerate() {
         cumulative += probability;
         if random_number < cumulative as u64 {
-            return (index, hash_result);
+            return index;
         }
     }
-    
-    (probabilities.len() - 1, hash_result)
+
+    probabilities.len() - 1
+}
+
+/// Walk the `SlotHashes` sysvar (newest slot first) and return the `(slot,
+/// hash)` for the smallest recorded slot strictly greater than `after_slot`.
+/// This is the slot hash that was unknowable at the time `after_slot` was
+/// committed. Whether that slot might have aged out of the sysvar entirely
+/// is `process_reveal_spin`'s job to bound via `MAX_REVEAL_DELAY_SLOTS`
+/// before calling this — trying to infer it here from gaps between entries
+/// can't tell a skipped slot from an evicted one.
+fn find_reveal_hash(slot_hashes_data: &[u8], after_slot: u64) -> Result<(u64, [u8; 32]), ProgramError> {
+    if slot_hashes_data.len() < 8 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let num_entries = u64::from_le_bytes(
+        slot_hashes_data[0..8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    ) as usize;
+
+    let mut offset = 8;
+    let mut candidate: Option<(u64, [u8; 32])> = None;
+
+    for _ in 0..num_entries {
+        if offset + 40 > slot_hashes_data.len() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let slot = u64::from_le_bytes(slot_hashes_data[offset..offset + 8].try_into().unwrap());
+        let slot_hash: [u8; 32] = slot_hashes_data[offset + 8..offset + 40].try_into().unwrap();
+        offset += 40;
+
+        if slot > after_slot {
+            // Entries are newest-first, so the last one we see here is the
+            // smallest slot still greater than `after_slot`.
+            candidate = Some((slot, slot_hash));
+        } else {
+            break;
+        }
+    }
+
+    // No entry newer than `after_slot`: the target slot hasn't landed yet.
+    // Reveal too early.
+    candidate.ok_or(ProgramError::InvalidArgument)
+}
+
+/// Check `account` really is the `SlotHashes` sysvar, so a caller can't hand
+/// in a self-owned account with a fabricated entry ground to land on a
+/// chosen outcome.
+fn check_slot_hashes_sysvar(account: &AccountInfo) -> Result<(), ProgramError> {
+    if *account.key != slot_hashes::ID {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Return the `(slot, hash)` of the newest entry in the `SlotHashes` sysvar.
+fn latest_slot_hash(slot_hashes_data: &[u8]) -> Result<(u64, [u8; 32]), ProgramError> {
+    if slot_hashes_data.len() < 48 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let num_entries = u64::from_le_bytes(
+        slot_hashes_data[0..8].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+    if num_entries == 0 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let slot = u64::from_le_bytes(slot_hashes_data[8..16].try_into().unwrap());
+    let slot_hash: [u8; 32] = slot_hashes_data[16..48].try_into().unwrap();
+    Ok((slot, slot_hash))
+}
+
+/// Load and sanity-check a player's spin state: the account must be the PDA
+/// derived from `(wheel, player)` and owned by this program, not just an
+/// account that happens to hold matching `wheel`/`player` fields (anyone can
+/// write those into an account they control).
+fn load_player_state(
+    player_state_account: &AccountInfo,
+    program_id: &Pubkey,
+    wheel: &Pubkey,
+    player: &Pubkey,
+) -> Result<PlayerSpinState, ProgramError> {
+    check_player_state_address(player_state_account, program_id, wheel, player)?;
+
+    let player_state = PlayerSpinState::try_from_slice(&player_state_account.try_borrow_data()?)
+        .map_err(map_to_program_error)?;
+
+    if !player_state.initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if player_state.wheel != *wheel || player_state.player != *player {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(player_state)
+}
+
+/// Prune epoch entries older than `MAX_EPOCH_HISTORY` and check/consume one
+/// spin against `max_spins_per_epoch` for the current epoch.
+fn record_epoch_spin(
+    player_state: &mut PlayerSpinState,
+    current_epoch: u64,
+    max_spins_per_epoch: u32,
+) -> Result<(), ProgramError> {
+    player_state
+        .epoch_spins
+        .retain(|&(epoch, _)| current_epoch.saturating_sub(epoch) < MAX_EPOCH_HISTORY);
+
+    match player_state.epoch_spins.iter_mut().find(|(epoch, _)| *epoch == current_epoch) {
+        Some((_, count)) => {
+            if *count >= max_spins_per_epoch {
+                return Err(ProgramError::Custom(1));
+            }
+            *count += 1;
+        }
+        None => {
+            if max_spins_per_epoch == 0 {
+                return Err(ProgramError::Custom(1));
+            }
+            player_state.epoch_spins.push((current_epoch, 1));
+        }
+    }
+
+    Ok(())
+}
+
+fn process_commit_spin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    commitment: [u8; 32],
+) -> Result<(), ProgramError> {
+    let account_info_iter = &mut accounts.iter();
+    let wheel_account = next_account_info(account_info_iter)?;
+    let player = next_account_info(account_info_iter)?;
+    let player_state_account = next_account_info(account_info_iter)?;
+
+    if !player.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut player_state = load_player_state(player_state_account, program_id, wheel_account.key, player.key)?;
+    player_state.user_commitment = commitment;
+    player_state.commit_slot = Clock::get()?.slot;
+    player_state.serialize(&mut *player_state_account.try_borrow_mut_data()?).map_err(map_to_program_error)?;
+
+    Ok(())
 }
 
 fn process_reveal_spin(
@@ -201,146 +471,319 @@ fn process_reveal_spin(
     let account_info_iter = &mut accounts.iter();
     let wheel_account = next_account_info(account_info_iter)?;
     let player = next_account_info(account_info_iter)?;
-    let slot_history = next_account_info(account_info_iter)?;
-    let recent_blockhashes = next_account_info(account_info_iter)?;
+    let slot_hashes = next_account_info(account_info_iter)?;
+    let player_state_account = next_account_info(account_info_iter)?;
 
     if !player.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let mut wheel_state = WheelState::try_from_slice(&wheel_account.try_borrow_data()?)?;
-    
+    let wheel_config = WheelConfig::try_from_slice(&wheel_account.try_borrow_data()?)?;
+    let mut player_state = load_player_state(player_state_account, program_id, wheel_account.key, player.key)?;
+
     // Verify the commitment matches
     let commitment = hash(&user_secret).to_bytes();
-    if commitment != wheel_state.user_commitment {
+    if commitment != player_state.user_commitment {
         return Err(ProgramError::InvalidArgument);
     }
 
-    // Get current slot and blockhash
-    let slot = arch_program::clock::Clock::get()?.slot;
-    let blockhash = recent_blockhashes.try_borrow_data()?[0..32].try_into().unwrap();
+    record_epoch_spin(&mut player_state, Clock::get()?.epoch, wheel_config.max_spins_per_epoch)?;
+
+    // Reject reveals so late that the target slot's hash may have already
+    // rolled out of the SlotHashes window — see `MAX_REVEAL_DELAY_SLOTS`.
+    if Clock::get()?.slot.saturating_sub(player_state.commit_slot) > MAX_REVEAL_DELAY_SLOTS {
+        return Err(ProgramError::InvalidArgument);
+    }
 
-    let (random_value, final_hash) = get_verifiable_random_value(
-        slot,
-        &blockhash,
+    check_slot_hashes_sysvar(slot_hashes)?;
+
+    // Bind the reveal to the slot hash of the first slot after the commit,
+    // instead of trusting a blockhash the player hands in. Both `target_hash`
+    // and `target_slot` are fixed as soon as that slot lands, so the result
+    // is fully determined before the player ever submits a reveal — using
+    // the reveal-time slot here would let them pick a winning submission
+    // slot by simulating off-chain.
+    let (target_slot, target_hash) = find_reveal_hash(&slot_hashes.try_borrow_data()?, player_state.commit_slot)?;
+
+    let (random_value, _) = get_verifiable_random_value(
+        target_slot,
+        &target_hash,
         &user_secret,
-        &wheel_state.probabilities
+        player.key,
+        &wheel_config.probabilities
     );
 
-    // Store verification data
-    wheel_state.last_slot = slot;
-    wheel_state.last_block_hash = blockhash;
-    wheel_state.last_spin_result = Some(random_value);
-    wheel_state.total_spins += 1;
-
-    wheel_state.serialize(&mut *wheel_account.try_borrow_mut_data()?)?;
-    
-    msg!("Wheel spin result: {}", wheel_state.prizes[random_value]);
-    msg!("Verification hash: {:?}", final_hash);
-    
+    player_state.history.push(SpinRecord {
+        slot: target_slot,
+        block_hash: target_hash,
+        user_entropy: user_secret,
+        player: *player.key,
+        result: random_value,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    if player_state.history.len() > MAX_SPIN_HISTORY {
+        player_state.history.remove(0);
+    }
+
+    player_state.prize_claimed = false;
+    player_state.serialize(&mut *player_state_account.try_borrow_mut_data()?)?;
+
+    msg!("Wheel spin result: {}", wheel_config.prizes[random_value]);
+    msg!("Verification hash: {:?}", target_hash);
+
     Ok(())
 }
 
-fn process_commit_spin(
+fn process_claim_prize(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    commitment: [u8; 32],
 ) -> Result<(), ProgramError> {
     let account_info_iter = &mut accounts.iter();
     let wheel_account = next_account_info(account_info_iter)?;
     let player = next_account_info(account_info_iter)?;
+    let escrow_account = next_account_info(account_info_iter)?;
+    let player_state_account = next_account_info(account_info_iter)?;
 
     if !player.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let mut wheel_state = WheelState::try_from_slice(&wheel_account.try_borrow_data()?)?;
-    wheel_state.user_commitment = commitment;
-    wheel_state.serialize(&mut *wheel_account.try_borrow_mut_data()?)?;
-    
+    let wheel_config = WheelConfig::try_from_slice(&wheel_account.try_borrow_data()?).map_err(map_to_program_error)?;
+    let mut player_state = load_player_state(player_state_account, program_id, wheel_account.key, player.key)?;
+
+    if *escrow_account.key != wheel_config.escrow {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let prize_index = player_state.history.last().ok_or(ProgramError::InvalidAccountData)?.result;
+    if player_state.prize_claimed {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if *escrow_account.owner != *program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let payout = wheel_config.payouts[prize_index];
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(escrow_account.data_len());
+    if escrow_account.lamports() < payout.saturating_add(rent_exempt_minimum) {
+        return Err(ProgramError::InsufficientFunds);
+    }
+    **escrow_account.try_borrow_mut_lamports()? -= payout;
+    **player.try_borrow_mut_lamports()? += payout;
+
+    player_state.prize_claimed = true;
+    player_state.serialize(&mut *player_state_account.try_borrow_mut_data()?).map_err(map_to_program_error)?;
+
+    msg!("Prize claimed: {} ({} lamports)", wheel_config.prizes[prize_index], payout);
     Ok(())
 }
 
-fn process_claim_prize(
+fn process_verify_spin(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
+    index: u8,
 ) -> Result<(), ProgramError> {
     let account_info_iter = &mut accounts.iter();
     let wheel_account = next_account_info(account_info_iter)?;
     let player = next_account_info(account_info_iter)?;
+    let player_state_account = next_account_info(account_info_iter)?;
+
+    // Anyone can call VerifySpin, so re-derive the PDA instead of trusting
+    // that `player_state_account` actually belongs to `player` — otherwise
+    // an attacker could point it at a self-crafted account and "verify" a
+    // fabricated history.
+    check_player_state_address(player_state_account, program_id, wheel_account.key, player.key)?;
+
+    let wheel_config = WheelConfig::try_from_slice(&wheel_account.try_borrow_data()?).map_err(map_to_program_error)?;
+    let player_state = PlayerSpinState::try_from_slice(&player_state_account.try_borrow_data()?).map_err(map_to_program_error)?;
+
+    let record = player_state
+        .history
+        .get(index as usize)
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    let (recomputed_result, _) = get_verifiable_random_value(
+        record.slot,
+        &record.block_hash,
+        &record.user_entropy,
+        &record.player,
+        &wheel_config.probabilities,
+    );
 
-    if !player.is_signer {
+    if recomputed_result != record.result {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    msg!("Spin {} verified: {}", index, wheel_config.prizes[record.result]);
+    Ok(())
+}
+
+fn process_batch_spin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    entropies: Vec<[u8; 32]>,
+) -> Result<(), ProgramError> {
+    let account_info_iter = &mut accounts.iter();
+    let wheel_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+
+    let wheel_config = WheelConfig::try_from_slice(&wheel_account.try_borrow_data()?)?;
+    if !wheel_config.initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Only the wheel's authority may drive a batch spin — see the
+    // `BatchSpin` doc comment for why an arbitrary player can't.
+    if !authority.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
+    if *authority.key != wheel_config.authority {
+        return Err(ProgramError::InvalidArgument);
+    }
 
-    let wheel_state = WheelState::try_from_slice(&wheel_account.try_borrow_data()?).map_err(map_to_program_error)?;
-    
-    if let Some(prize_index) = wheel_state.last_spin_result {
-        msg!("Prize claimed: {}", wheel_state.prizes[prize_index]);
-        Ok(())
-    } else {
-        msg!("No prize to claim");
-        Err(ProgramError::InvalidAccountData)
+    // Everything left is `(player, player_state)` pairs, one per entry in
+    // `entropies`, followed by the slot-hashes sysvar as the last account.
+    // Each pair's state is loaded from and written back to its account in
+    // turn, which tolerates the same account handle appearing more than once
+    // in that list.
+    let remaining: Vec<&AccountInfo> = account_info_iter.collect();
+    let (slot_hashes_account, player_pairs) = remaining
+        .split_last()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    if player_pairs.len() != entropies.len() * 2 {
+        return Err(ProgramError::InvalidArgument);
     }
+
+    check_slot_hashes_sysvar(slot_hashes_account)?;
+    let (slot, block_hash) = latest_slot_hash(&slot_hashes_account.try_borrow_data()?)?;
+    let timestamp = Clock::get()?.unix_timestamp;
+    let current_epoch = Clock::get()?.epoch;
+
+    for (pair, user_entropy) in player_pairs.chunks_exact(2).zip(entropies.iter()) {
+        let player = pair[0];
+        let player_state_account = pair[1];
+
+        let mut player_state = load_player_state(player_state_account, program_id, wheel_account.key, player.key)?;
+        record_epoch_spin(&mut player_state, current_epoch, wheel_config.max_spins_per_epoch)?;
+
+        let (random_value, _) = get_verifiable_random_value(
+            slot,
+            &block_hash,
+            user_entropy,
+            player.key,
+            &wheel_config.probabilities,
+        );
+
+        player_state.history.push(SpinRecord {
+            slot,
+            block_hash,
+            user_entropy: *user_entropy,
+            player: *player.key,
+            result: random_value,
+            timestamp,
+        });
+        if player_state.history.len() > MAX_SPIN_HISTORY {
+            player_state.history.remove(0);
+        }
+        player_state.prize_claimed = false;
+        player_state.serialize(&mut *player_state_account.try_borrow_mut_data()?).map_err(map_to_program_error)?;
+
+        msg!("Batch spin result for {}: {}", player.key, wheel_config.prizes[random_value]);
+    }
+
+    Ok(())
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use arch_program::{
-//         account::AccountInfo,
-//         pubkey::Pubkey,
-//         utxo::UtxoMeta,
-//     };
-//     use std::cell::RefCell;
-//     use std::rc::Rc;
-
-//     fn create_test_account(
-//         key: Pubkey,
-//         owner: Pubkey,
-//         size: usize,
-//         is_signer: bool,
-//     ) -> AccountInfo {
-//         let data = RefCell::new(vec![0; size]);
-//         let utxo = UtxoMeta::from([0; 32], 0);
-        
-//         AccountInfo::new(
-//             &key,
-//             &data,
-//             &owner,
-//             &utxo,
-//             is_signer,
-//             true,  // is_writable
-//             false, // is_executable
-//         )
-//     }
-
-//     #[test]
-//     fn test_initialize_wheel() {
-//         let program_id = Pubkey::new_unique();
-//         let wheel_key = Pubkey::new_unique();
-//         let authority_key = Pubkey::new_unique();
-
-//         // Create accounts with RefCell for proper data management
-//         let wheel_account = create_test_account(wheel_key, program_id, 1024, false);
-//         let authority_account = create_test_account(authority_key, program_id, 0, true);
-
-//         let prizes = vec!["Prize1".to_string(), "Prize2".to_string()];
-//         let probabilities = vec![50, 50];
-
-//         let accounts = vec![wheel_account.clone(), authority_account];
-
-//         let result = process_initialize(&program_id, &accounts, prizes.clone(), probabilities.clone());
-//         assert!(result.is_ok());
-
-//         // Verify state
-//         let wheel_state = WheelState::try_from_slice(&wheel_account.try_borrow_data().unwrap())
-//             .map_err(map_to_program_error)
-//             .unwrap();
-        
-//         assert!(wheel_state.initialized);
-//         assert_eq!(wheel_state.prizes, prizes);
-//         assert_eq!(wheel_state.probabilities, probabilities);
-//         assert_eq!(wheel_state.total_spins, 0);
-//     }
-// }
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Builds a `SlotHashes`-layout buffer (newest slot first) from `(slot,
+    // hash)` pairs, matching what `find_reveal_hash` parses.
+    fn slot_hashes_data(entries: &[(u64, [u8; 32])]) -> Vec<u8> {
+        let mut data = (entries.len() as u64).to_le_bytes().to_vec();
+        for (slot, hash) in entries {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(hash);
+        }
+        data
+    }
+
+    #[test]
+    fn find_reveal_hash_returns_smallest_slot_after_commit() {
+        let data = slot_hashes_data(&[(105, [5u8; 32]), (102, [2u8; 32]), (100, [0u8; 32])]);
+        let (slot, hash) = find_reveal_hash(&data, 100).unwrap();
+        assert_eq!(slot, 102);
+        assert_eq!(hash, [2u8; 32]);
+    }
+
+    #[test]
+    fn find_reveal_hash_rejects_target_not_yet_landed() {
+        let data = slot_hashes_data(&[(100, [0u8; 32])]);
+        assert!(find_reveal_hash(&data, 100).is_err());
+    }
+
+    #[test]
+    fn find_reveal_hash_accepts_exact_boundary() {
+        // The oldest retained entry is exactly `after_slot + 1`.
+        let data = slot_hashes_data(&[(105, [5u8; 32]), (101, [1u8; 32])]);
+        let (slot, _) = find_reveal_hash(&data, 100).unwrap();
+        assert_eq!(slot, 101);
+    }
+
+    #[test]
+    fn find_reveal_hash_accepts_boundary_after_skipped_slot() {
+        // Slot 101 was skipped — the oldest retained entry is 102, still a
+        // legitimate in-window reveal.
+        let data = slot_hashes_data(&[(105, [5u8; 32]), (102, [2u8; 32])]);
+        let (slot, _) = find_reveal_hash(&data, 100).unwrap();
+        assert_eq!(slot, 102);
+    }
+
+    #[test]
+    fn find_reveal_hash_rejects_truncated_data() {
+        let mut data = slot_hashes_data(&[(105, [5u8; 32])]);
+        data.truncate(data.len() - 1);
+        assert!(find_reveal_hash(&data, 100).is_err());
+    }
+
+    fn player_spin_state() -> PlayerSpinState {
+        PlayerSpinState {
+            initialized: true,
+            wheel: Pubkey::default(),
+            player: Pubkey::default(),
+            user_commitment: [0u8; 32],
+            commit_slot: 0,
+            prize_claimed: false,
+            history: Vec::new(),
+            epoch_spins: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn record_epoch_spin_caps_spins_per_epoch() {
+        let mut state = player_spin_state();
+        record_epoch_spin(&mut state, 10, 2).unwrap();
+        record_epoch_spin(&mut state, 10, 2).unwrap();
+        assert!(record_epoch_spin(&mut state, 10, 2).is_err());
+        assert_eq!(state.epoch_spins, vec![(10, 2)]);
+    }
+
+    #[test]
+    fn record_epoch_spin_rejects_when_cap_is_zero() {
+        let mut state = player_spin_state();
+        assert!(record_epoch_spin(&mut state, 10, 0).is_err());
+    }
+
+    #[test]
+    fn record_epoch_spin_prunes_entries_older_than_max_epoch_history() {
+        let mut state = player_spin_state();
+        record_epoch_spin(&mut state, 10, 5).unwrap();
+        record_epoch_spin(&mut state, 10 + MAX_EPOCH_HISTORY, 5).unwrap();
+        // The epoch-10 entry is now `MAX_EPOCH_HISTORY` epochs behind and
+        // should have been pruned, leaving only the current epoch.
+        assert_eq!(state.epoch_spins, vec![(10 + MAX_EPOCH_HISTORY, 1)]);
+    }
+}